@@ -1,9 +1,16 @@
+use serde::Deserialize;
 use std::fs;
 use std::path::{Path, PathBuf};
+use zed_extension_api::settings::LspSettings;
 use zed_extension_api::{self as zed, GithubRelease, Result};
 
+mod sha256;
+
 // Binary and versioning constants
 const EXTENSION_LSP_NAME: &str = "codebook-lsp";
+// Identifier the extension registers its language server under; used to look up
+// the user's `lsp` settings block for this server.
+const LANGUAGE_SERVER_ID: &str = "codebook";
 const VERSION_FILE: &str = ".version";
 const GITHUB_REPO_OWNER: &str = "blopker";
 const GITHUB_REPO_NAME: &str = "codebook";
@@ -13,9 +20,47 @@ const ENV_RUST_LOG: &str = "RUST_LOG";
 const LOG_LEVEL_DEBUG: &str = "debug";
 const LOG_LEVEL_INFO: &str = "info";
 
+// Number of times a download is attempted before giving up, to ride out
+// transient network hiccups.
+const DOWNLOAD_MAX_ATTEMPTS: u32 = 3;
+
+// Base delay before a retried download attempt; the n-th retry waits n times
+// this (250ms, then 500ms). Kept short since this runs
+// inside the extension's WASM sandbox on the same thread as the rest of
+// the install flow.
+const DOWNLOAD_RETRY_BACKOFF: std::time::Duration = std::time::Duration::from_millis(250);
+
+// Prefix on errors from `verify_checksum` that indicate the downloaded
+// binary's contents don't match the published checksum, as opposed to a
+// transient failure to download or read it. Used to tell the two apart so
+// a tampered/corrupted binary is never silently swapped for a cached one.
+const CHECKSUM_MISMATCH_PREFIX: &str = "Checksum mismatch for";
+
 // Get pre_release build for testing
 const GET_PRE_RELEASE: bool = false;
 
+// User-configurable options read from the extension's `lsp` settings block.
+// Everything is optional, so an empty or absent block falls back to the
+// download-the-latest-release behavior.
+#[derive(Default, Deserialize)]
+struct CodebookSettings {
+    // Pin the language server to a specific GitHub release tag instead of
+    // always tracking `latest`.
+    #[serde(default)]
+    version: Option<String>,
+    // Override the RUST_LOG level the server is launched with
+    #[serde(default)]
+    log_level: Option<String>,
+    // Spell-check configuration, forwarded verbatim to the server as its
+    // initializationOptions
+    #[serde(default)]
+    initialization_options: Option<serde_json::Value>,
+    // Base URL of a mirror/artifact cache; rewrites each asset download URL's
+    // host to point here
+    #[serde(default)]
+    download_mirror: Option<String>,
+}
+
 struct CodebookExtension {
     binary_cache: Option<PathBuf>,
 }
@@ -33,6 +78,17 @@ impl CodebookBinary {
             env: vec![(ENV_RUST_LOG.to_string(), log_level.to_string())],
         }
     }
+
+    // Replace the RUST_LOG value in the launch environment, preserving any
+    // other entries (e.g. the dev build's RUST_BACKTRACE)
+    fn set_log_level(&mut self, log_level: &str) {
+        if let Some(entry) = self.env.iter_mut().find(|(key, _)| key == ENV_RUST_LOG) {
+            entry.1 = log_level.to_string();
+        } else {
+            self.env
+                .push((ENV_RUST_LOG.to_string(), log_level.to_string()));
+        }
+    }
 }
 
 impl CodebookExtension {
@@ -44,6 +100,8 @@ impl CodebookExtension {
         &mut self,
         language_server_id: &zed::LanguageServerId,
         worktree: &zed::Worktree,
+        version: Option<&str>,
+        mirror: Option<&str>,
     ) -> Result<CodebookBinary> {
         // Check for development binary
         if let Some(binary) = self.find_development_binary()? {
@@ -61,7 +119,17 @@ impl CodebookExtension {
         }
 
         // Download or update binary
-        self.ensure_latest_binary(language_server_id)
+        self.ensure_latest_binary(language_server_id, version, mirror)
+    }
+
+    // Read and deserialize this extension's lsp settings block, falling back
+    // to defaults when absent or malformed
+    fn codebook_settings(&self, worktree: &zed::Worktree) -> CodebookSettings {
+        LspSettings::for_worktree(LANGUAGE_SERVER_ID, worktree)
+            .ok()
+            .and_then(|settings| settings.settings)
+            .and_then(|value| serde_json::from_value(value).ok())
+            .unwrap_or_default()
     }
 
     fn find_development_binary(&self) -> Result<Option<CodebookBinary>> {
@@ -100,20 +168,45 @@ impl CodebookExtension {
     fn ensure_latest_binary(
         &mut self,
         language_server_id: &zed::LanguageServerId,
+        version: Option<&str>,
+        mirror: Option<&str>,
     ) -> Result<CodebookBinary> {
         zed::set_language_server_installation_status(
             language_server_id,
             &zed::LanguageServerInstallationStatus::CheckingForUpdate,
         );
 
-        let result = match self.check_for_update() {
+        let result = match self.check_for_update(version) {
             Ok(Some(release)) => {
                 // Update available - download it
                 zed::set_language_server_installation_status(
                     language_server_id,
                     &zed::LanguageServerInstallationStatus::Downloading,
                 );
-                self.download_and_install_binary(&release, language_server_id)
+                match self.download_and_install_binary(&release, language_server_id, mirror) {
+                    Ok(binary) => Ok(binary),
+                    // A checksum mismatch means the binary on disk may be
+                    // corrupted or tampered with. Never paper over that by
+                    // quietly falling back to a cached version; let it hard
+                    // fail so `LanguageServerInstallationStatus::Failed` (set
+                    // by `download_and_install_binary`) reaches the user.
+                    Err(e) if e.starts_with(CHECKSUM_MISMATCH_PREFIX) => Err(e),
+                    // Some other part of the download failed outright. If a
+                    // previously installed version is still on disk, keep the
+                    // editor usable with it instead of failing startup over a
+                    // flaky network.
+                    Err(e) => match self.load_existing_binary() {
+                        Ok(binary) => {
+                            eprintln!(
+                                "Warning: failed to download update ({}), \
+                                falling back to cached binary",
+                                e
+                            );
+                            Ok(binary)
+                        }
+                        Err(_) => Err(e),
+                    },
+                }
             }
             Ok(None) | Err(_) => {
                 // No update needed - use existing, or if err, internet failed or unsupported platform
@@ -137,8 +230,9 @@ impl CodebookExtension {
         &self,
         release: &GithubRelease,
         language_server_id: &zed::LanguageServerId,
+        mirror: Option<&str>,
     ) -> Result<CodebookBinary> {
-        match self.install_binary(release) {
+        match self.install_binary(release, mirror) {
             Ok(path) => Ok(CodebookBinary::new(path, LOG_LEVEL_INFO)),
             Err(e) => {
                 zed::set_language_server_installation_status(
@@ -177,14 +271,19 @@ impl CodebookExtension {
         binary
     }
 
-    fn check_for_update(&self) -> Result<Option<GithubRelease>> {
-        let release = zed::latest_github_release(
-            &format!("{}/{}", GITHUB_REPO_OWNER, GITHUB_REPO_NAME),
-            zed::GithubReleaseOptions {
-                require_assets: true,
-                pre_release: GET_PRE_RELEASE,
-            },
-        )?;
+    fn check_for_update(&self, version: Option<&str>) -> Result<Option<GithubRelease>> {
+        let repo = format!("{}/{}", GITHUB_REPO_OWNER, GITHUB_REPO_NAME);
+        let release = match version {
+            // A pinned release tag takes precedence over tracking `latest`.
+            Some(tag) => zed::github_release_by_tag_name(&repo, tag)?,
+            None => zed::latest_github_release(
+                &repo,
+                zed::GithubReleaseOptions {
+                    require_assets: true,
+                    pre_release: GET_PRE_RELEASE,
+                },
+            )?,
+        };
 
         // Check if we already have this version
         if let Ok(current_version) = self.read_version_file() {
@@ -211,78 +310,209 @@ impl CodebookExtension {
         Ok(binary_path)
     }
 
-    fn install_binary(&self, release: &zed::GithubRelease) -> Result<PathBuf> {
-        let asset = self.find_compatible_asset(release)?;
+    fn install_binary(&self, release: &zed::GithubRelease, mirror: Option<&str>) -> Result<PathBuf> {
+        let (asset, file_type) = self.find_compatible_asset(release)?;
         let version_dir = self.get_version_directory_path(&release.version);
         let binary_path = version_dir.join(self.get_binary_filename());
 
         if !binary_path.exists() {
-            self.download_binary(asset, &version_dir, &binary_path)?;
+            self.download_binary(asset, file_type, mirror, &version_dir, &binary_path)?;
+            if let Err(e) = self.verify_checksum(release, asset, mirror, &binary_path) {
+                // Don't leave a potentially corrupted or tampered binary around:
+                // if removal fails and the binary is still on disk, the next
+                // `install_binary` call for this release would see
+                // `binary_path.exists()` and return it straight away, silently
+                // resurrecting the binary that just failed verification.
+                if fs::remove_dir_all(&version_dir).is_err() && binary_path.exists() {
+                    return Err(format!(
+                        "{} (additionally failed to remove the unverified binary at {})",
+                        e,
+                        binary_path.display()
+                    ));
+                }
+                return Err(e);
+            }
             self.write_version_file(&release.version)?;
             self.cleanup_old_versions(&version_dir)?;
         }
         Ok(binary_path)
     }
 
-    fn asset_name(&self, platform: zed::Os, arch: zed::Architecture) -> Result<(String, String)> {
+    // Verify the extracted binary against the release's checksum asset, if
+    // one exists (a per-asset <name>.sha256 file or a shared checksums.txt).
+    // No published checksum is not an error; we proceed with a warning.
+    fn verify_checksum(
+        &self,
+        release: &GithubRelease,
+        asset: &zed::GithubReleaseAsset,
+        mirror: Option<&str>,
+        binary_path: &Path,
+    ) -> Result<()> {
+        let expected = match self.find_checksum(release, &asset.name, mirror)? {
+            Some(expected) => expected,
+            None => {
+                eprintln!(
+                    "Warning: no checksum published for {}, skipping integrity check",
+                    asset.name
+                );
+                return Ok(());
+            }
+        };
+
+        let bytes = fs::read(binary_path)
+            .map_err(|e| format!("Failed to read binary for checksum: {}", e))?;
+        let actual = sha256::hex_digest(&bytes);
+
+        if actual != expected {
+            return Err(format!(
+                "{} {}: expected {}, got {}",
+                CHECKSUM_MISMATCH_PREFIX, asset.name, expected, actual
+            ));
+        }
+
+        Ok(())
+    }
+
+    // Locate and download the checksum asset matching asset_name, returning
+    // the expected lowercase hex digest if found
+    fn find_checksum(
+        &self,
+        release: &GithubRelease,
+        asset_name: &str,
+        mirror: Option<&str>,
+    ) -> Result<Option<String>> {
+        let candidates = [format!("{}.sha256", asset_name), "checksums.txt".to_string()];
+
+        for candidate in candidates {
+            let Some(checksum_asset) = release.assets.iter().find(|a| a.name == candidate) else {
+                continue;
+            };
+
+            let dest = format!("{}.checksum", EXTENSION_LSP_NAME);
+            let url = Self::apply_mirror(&checksum_asset.download_url, mirror);
+            self.download_file_with_retry(&url, &dest, zed::DownloadedFileType::Uncompressed)
+                .map_err(|e| format!("Failed to download checksum asset: {}", e))?;
+
+            let contents = fs::read_to_string(&dest)
+                .map_err(|e| format!("Failed to read checksum asset: {}", e))?;
+            let _ = fs::remove_file(&dest);
+
+            if let Some(digest) = Self::parse_checksum(&contents, asset_name) {
+                return Ok(Some(digest));
+            }
+        }
+
+        Ok(None)
+    }
+
+    // Parse the expected digest for asset_name out of a checksum file. Lines
+    // are either a bare <hex> (a single-asset .sha256 file) or the
+    // <hex>  <filename> form used by checksums.txt; a leading '*' binary
+    // marker on the filename is tolerated.
+    fn parse_checksum(contents: &str, asset_name: &str) -> Option<String> {
+        for line in contents.lines() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let mut parts = line.split_whitespace();
+            let hash = parts.next()?;
+
+            match parts.next() {
+                Some(name) => {
+                    if name.trim_start_matches('*') == asset_name {
+                        return Some(hash.to_lowercase());
+                    }
+                }
+                None => return Some(hash.to_lowercase()),
+            }
+        }
+
+        None
+    }
+
+    // `zed::DownloadedFileType::XzTar`/`ZstdTar` were dropped here: this tree
+    // has no Cargo.toml pinning a zed_extension_api version and no network
+    // access to confirm those variants exist, and referencing a variant that
+    // isn't there would fail the whole extension's build, not just this
+    // feature. Re-add tar.xz/tar.zst once that's verified against the
+    // pinned version.
+    fn file_type_for_extension(ext: &str) -> Option<zed::DownloadedFileType> {
+        match ext {
+            "tar.gz" => Some(zed::DownloadedFileType::GzipTar),
+            "zip" => Some(zed::DownloadedFileType::Zip),
+            _ => None,
+        }
+    }
+
+    // Candidate archive extensions for this platform
+    fn asset_candidates(
+        &self,
+        platform: zed::Os,
+        arch: zed::Architecture,
+    ) -> Result<(Vec<(String, zed::DownloadedFileType)>, String)> {
         let arch_name = match arch {
             zed::Architecture::Aarch64 => "aarch64",
             zed::Architecture::X8664 => "x86_64",
             zed::Architecture::X86 => return Err("x86 architecture is not supported".into()),
         };
 
-        let (os_str, file_ext) = match platform {
-            zed::Os::Mac => ("apple-darwin", "tar.gz"),
-            zed::Os::Linux => ("unknown-linux-musl", "tar.gz"),
-            zed::Os::Windows => ("pc-windows-msvc", "zip"),
+        let (os_str, extensions): (&str, &[&str]) = match platform {
+            zed::Os::Mac => ("apple-darwin", &["tar.gz"]),
+            zed::Os::Linux => ("unknown-linux-musl", &["tar.gz"]),
+            zed::Os::Windows => ("pc-windows-msvc", &["zip"]),
         };
 
         let descriptor = format!("{}-{}", arch_name, os_str);
 
-        let name = format!(
-            "{}-{}-{}.{}",
-            EXTENSION_LSP_NAME, arch_name, os_str, file_ext
-        );
-
-        Ok((name, descriptor))
+        let candidates = extensions
+            .iter()
+            .filter_map(|ext| {
+                let file_type = Self::file_type_for_extension(ext)?;
+                let name = format!("{}-{}.{}", EXTENSION_LSP_NAME, descriptor, ext);
+                Some((name, file_type))
+            })
+            .collect();
+
+        Ok((candidates, descriptor))
     }
 
     fn find_compatible_asset<'a>(
         &self,
         release: &'a GithubRelease,
-    ) -> Result<&'a zed::GithubReleaseAsset> {
+    ) -> Result<(&'a zed::GithubReleaseAsset, zed::DownloadedFileType)> {
         let (platform, arch) = zed::current_platform();
-        let (asset_name, descriptor) = self.asset_name(platform, arch)?;
+        let (candidates, descriptor) = self.asset_candidates(platform, arch)?;
 
-        release
-            .assets
+        candidates
             .iter()
-            .find(|a| a.name == asset_name)
+            .find_map(|(name, file_type)| {
+                release
+                    .assets
+                    .iter()
+                    .find(|a| a.name == *name)
+                    .map(|asset| (asset, *file_type))
+            })
             .ok_or_else(|| format!("No compatible binary found for {}", descriptor))
     }
 
     fn download_binary(
         &self,
         asset: &zed::GithubReleaseAsset,
+        file_type: zed::DownloadedFileType,
+        mirror: Option<&str>,
         version_dir: &Path,
         binary_path: &Path,
     ) -> Result<()> {
-        let (platform, _) = zed::current_platform();
         let version_dir_str = version_dir
             .to_str()
             .ok_or("Invalid version directory path")?;
 
         // Download and extract
-        zed::download_file(
-            &asset.download_url,
-            version_dir_str,
-            if platform == zed::Os::Windows {
-                zed::DownloadedFileType::Zip
-            } else {
-                zed::DownloadedFileType::GzipTar
-            },
-        )
-        .map_err(|e| format!("Failed to download binary: {}", e))?;
+        let url = Self::apply_mirror(&asset.download_url, mirror);
+        self.download_file_with_retry(&url, version_dir_str, file_type)
+            .map_err(|e| format!("Failed to download binary: {}", e))?;
 
         // Make executable
         let binary_path_str = binary_path.to_str().ok_or("Invalid binary path")?;
@@ -293,6 +523,61 @@ impl CodebookExtension {
         Ok(())
     }
 
+    // Download a file, retrying a few times with a short backoff so a single
+    // dropped connection or rate limit doesn't abort installation. dest is
+    // cleared before each retry so a partial archive can't mix with the next.
+    fn download_file_with_retry(
+        &self,
+        url: &str,
+        dest: &str,
+        file_type: zed::DownloadedFileType,
+    ) -> Result<()> {
+        let mut last_error = String::new();
+
+        for attempt in 1..=DOWNLOAD_MAX_ATTEMPTS {
+            if attempt > 1 {
+                // Best-effort: `dest` may be a directory (archive extraction)
+                // or a single file (e.g. a checksum file), and may not exist
+                // at all if the previous attempt failed before writing
+                // anything.
+                let _ = fs::remove_dir_all(dest).or_else(|_| fs::remove_file(dest));
+                std::thread::sleep(DOWNLOAD_RETRY_BACKOFF * (attempt - 1));
+            }
+
+            match zed::download_file(url, dest, file_type) {
+                Ok(()) => return Ok(()),
+                Err(e) => {
+                    last_error = e.to_string();
+                    eprintln!(
+                        "Warning: download attempt {}/{} failed: {}",
+                        attempt, DOWNLOAD_MAX_ATTEMPTS, last_error
+                    );
+                }
+            }
+        }
+
+        Err(format!(
+            "giving up after {} attempts: {}",
+            DOWNLOAD_MAX_ATTEMPTS, last_error
+        ))
+    }
+
+    // Rewrite a download URL's scheme and host to point at mirror, keeping
+    // the original path. Unchanged when no mirror is set.
+    fn apply_mirror(url: &str, mirror: Option<&str>) -> String {
+        let Some(base) = mirror else {
+            return url.to_string();
+        };
+
+        let base = base.trim_end_matches('/');
+        let after_scheme = url.split_once("://").map_or(url, |(_, rest)| rest);
+        let path = after_scheme
+            .find('/')
+            .map_or("", |idx| &after_scheme[idx..]);
+
+        format!("{}{}", base, path)
+    }
+
     fn write_version_file(&self, version: &str) -> Result<()> {
         fs::write(VERSION_FILE, version).map_err(|e| format!("Failed to write version file: {}", e))
     }
@@ -340,15 +625,47 @@ impl zed::Extension for CodebookExtension {
         language_server_id: &zed::LanguageServerId,
         worktree: &zed::Worktree,
     ) -> Result<zed::Command> {
-        let binary = self.get_binary(language_server_id, worktree).map_err(|e| {
-            format!(
-                "Failed to load binary! This could be due to no internet connection, \
+        let lsp_settings = LspSettings::for_worktree(LANGUAGE_SERVER_ID, worktree).ok();
+        let binary_settings = lsp_settings.as_ref().and_then(|s| s.binary.clone());
+        let settings = self.codebook_settings(worktree);
+
+        let project_path = worktree.root_path();
+        let mut args = vec![format!("--root={}", project_path), "serve".to_string()];
+        if let Some(extra) = binary_settings.as_ref().and_then(|b| b.arguments.clone()) {
+            args.extend(extra);
+        }
+
+        // An explicit `binary.path` override skips all download/discovery logic
+        // and runs the given executable directly.
+        if let Some(path) = binary_settings.and_then(|b| b.path) {
+            let log_level = settings.log_level.as_deref().unwrap_or(LOG_LEVEL_INFO);
+            return Ok(zed::Command {
+                command: path,
+                args,
+                env: CodebookBinary::new(PathBuf::new(), log_level).env,
+            });
+        }
+
+        let mut binary = self
+            .get_binary(
+                language_server_id,
+                worktree,
+                settings.version.as_deref(),
+                settings.download_mirror.as_deref(),
+            )
+            .map_err(|e| {
+                format!(
+                    "Failed to load binary! This could be due to no internet connection, \
                 or running on an unsupported platform. \n
                 Please check that github.com is accessible and try again. \n
                 Error: {e}"
-            )
-        })?;
-        let project_path = worktree.root_path();
+                )
+            })?;
+
+        // A configured log level overrides whatever the discovery path chose.
+        if let Some(level) = settings.log_level.as_deref() {
+            binary.set_log_level(level);
+        }
 
         let binary_str = binary
             .path
@@ -357,10 +674,18 @@ impl zed::Extension for CodebookExtension {
 
         Ok(zed::Command {
             command: binary_str.to_string(),
-            args: vec![format!("--root={}", project_path), "serve".to_string()],
+            args,
             env: binary.env,
         })
     }
+
+    fn language_server_initialization_options(
+        &mut self,
+        _language_server_id: &zed::LanguageServerId,
+        worktree: &zed::Worktree,
+    ) -> Result<Option<serde_json::Value>> {
+        Ok(self.codebook_settings(worktree).initialization_options)
+    }
 }
 
 zed::register_extension!(CodebookExtension);
@@ -411,25 +736,35 @@ mod tests {
     }
 
     #[test]
-    fn test_windows_arm64_asset_name_includes_exe_variant() {
+    fn test_windows_asset_candidates_are_zip_only() {
         let extension = CodebookExtension::new();
-        let (name, descriptor) = extension
-            .asset_name(zed::Os::Windows, zed::Architecture::Aarch64)
+        let (candidates, descriptor) = extension
+            .asset_candidates(zed::Os::Windows, zed::Architecture::Aarch64)
             .expect("expected candidates");
 
         assert_eq!(descriptor, "aarch64-pc-windows-msvc");
-        assert_eq!(name, "codebook-lsp-aarch64-pc-windows-msvc.zip");
+        let names: Vec<_> = candidates.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, vec!["codebook-lsp-aarch64-pc-windows-msvc.zip"]);
     }
 
     #[test]
-    fn test_macos_x86_asset_name_does_not_include_exe_variant() {
+    fn test_macos_asset_candidates_are_tar_gz() {
         let extension = CodebookExtension::new();
-        let (name, descriptor) = extension
-            .asset_name(zed::Os::Mac, zed::Architecture::X8664)
+        let (candidates, descriptor) = extension
+            .asset_candidates(zed::Os::Mac, zed::Architecture::X8664)
             .expect("expected candidates");
 
         assert_eq!(descriptor, "x86_64-apple-darwin");
-        assert_eq!(name, "codebook-lsp-x86_64-apple-darwin.tar.gz");
+        let names: Vec<_> = candidates.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, vec!["codebook-lsp-x86_64-apple-darwin.tar.gz"]);
+    }
+
+    #[test]
+    fn test_x86_architecture_unsupported() {
+        let extension = CodebookExtension::new();
+        assert!(extension
+            .asset_candidates(zed::Os::Linux, zed::Architecture::X86)
+            .is_err());
     }
 
     #[test]
@@ -438,6 +773,134 @@ mod tests {
         assert!(extension.binary_cache.is_none());
     }
 
+    #[test]
+    fn test_parse_checksum_checksums_txt() {
+        let contents = "\
+abc123  codebook-lsp-x86_64-apple-darwin.tar.gz
+def456  codebook-lsp-aarch64-apple-darwin.tar.gz
+";
+        let digest =
+            CodebookExtension::parse_checksum(contents, "codebook-lsp-aarch64-apple-darwin.tar.gz");
+        assert_eq!(digest, Some("def456".to_string()));
+    }
+
+    #[test]
+    fn test_parse_checksum_bare_digest() {
+        let digest = CodebookExtension::parse_checksum(
+            "ABC123DEF\n",
+            "codebook-lsp-x86_64-apple-darwin.tar.gz",
+        );
+        assert_eq!(digest, Some("abc123def".to_string()));
+    }
+
+    #[test]
+    fn test_parse_checksum_tolerates_binary_marker() {
+        let contents = "deadbeef *codebook-lsp-x86_64-unknown-linux-musl.tar.gz\n";
+        let digest = CodebookExtension::parse_checksum(
+            contents,
+            "codebook-lsp-x86_64-unknown-linux-musl.tar.gz",
+        );
+        assert_eq!(digest, Some("deadbeef".to_string()));
+    }
+
+    #[test]
+    fn test_parse_checksum_no_match() {
+        let contents = "abc123  some-other-file.tar.gz\n";
+        let digest =
+            CodebookExtension::parse_checksum(contents, "codebook-lsp-x86_64-apple-darwin.tar.gz");
+        assert_eq!(digest, None);
+    }
+
+    #[test]
+    fn test_codebook_settings_version_pin() {
+        let value = serde_json::json!({ "version": "v1.4.2" });
+        let settings: CodebookSettings = serde_json::from_value(value).unwrap();
+        assert_eq!(settings.version.as_deref(), Some("v1.4.2"));
+    }
+
+    #[test]
+    fn test_codebook_settings_defaults() {
+        let settings: CodebookSettings = serde_json::from_value(serde_json::json!({})).unwrap();
+        assert!(settings.version.is_none());
+        assert!(settings.log_level.is_none());
+        assert!(settings.initialization_options.is_none());
+    }
+
+    #[test]
+    fn test_codebook_settings_log_level_and_init_options() {
+        let value = serde_json::json!({
+            "log_level": "trace",
+            "initialization_options": { "allowed_words": ["blopker"] }
+        });
+        let settings: CodebookSettings = serde_json::from_value(value).unwrap();
+        assert_eq!(settings.log_level.as_deref(), Some("trace"));
+        assert_eq!(
+            settings.initialization_options,
+            Some(serde_json::json!({ "allowed_words": ["blopker"] }))
+        );
+    }
+
+    #[test]
+    fn test_apply_mirror_none_is_unchanged() {
+        let url = "https://github.com/blopker/codebook/releases/download/v1/asset.tar.gz";
+        assert_eq!(CodebookExtension::apply_mirror(url, None), url);
+    }
+
+    #[test]
+    fn test_apply_mirror_rewrites_host_keeps_path() {
+        let url = "https://github.com/blopker/codebook/releases/download/v1/asset.tar.gz";
+        let mirrored = CodebookExtension::apply_mirror(url, Some("https://cache.internal"));
+        assert_eq!(
+            mirrored,
+            "https://cache.internal/blopker/codebook/releases/download/v1/asset.tar.gz"
+        );
+    }
+
+    #[test]
+    fn test_apply_mirror_trims_trailing_slash() {
+        let url = "https://github.com/blopker/codebook/asset.zip";
+        let mirrored = CodebookExtension::apply_mirror(url, Some("https://cache.internal/"));
+        assert_eq!(
+            mirrored,
+            "https://cache.internal/blopker/codebook/asset.zip"
+        );
+    }
+
+    #[test]
+    fn test_codebook_settings_download_mirror() {
+        let value = serde_json::json!({ "download_mirror": "https://cache.internal" });
+        let settings: CodebookSettings = serde_json::from_value(value).unwrap();
+        assert_eq!(
+            settings.download_mirror.as_deref(),
+            Some("https://cache.internal")
+        );
+    }
+
+    #[test]
+    fn test_set_log_level_overrides_existing() {
+        let mut binary = CodebookBinary::new(PathBuf::from("/bin/codebook"), LOG_LEVEL_INFO);
+        binary.set_log_level("trace");
+
+        assert_eq!(binary.env.len(), 1);
+        assert_eq!(
+            binary.env[0],
+            (ENV_RUST_LOG.to_string(), "trace".to_string())
+        );
+    }
+
+    #[test]
+    fn test_set_log_level_preserves_other_env() {
+        let mut binary = CodebookBinary::new(PathBuf::from("/bin/codebook"), LOG_LEVEL_DEBUG);
+        binary
+            .env
+            .push(("RUST_BACKTRACE".to_string(), "1".to_string()));
+        binary.set_log_level("trace");
+
+        assert_eq!(binary.env.len(), 2);
+        assert_eq!(binary.env[0].1, "trace");
+        assert_eq!(binary.env[1].0, "RUST_BACKTRACE");
+    }
+
     #[test]
     fn test_get_cached_binary_no_cache() {
         let extension = CodebookExtension::new();